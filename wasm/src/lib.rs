@@ -1,26 +1,190 @@
-use std::{borrow::BorrowMut, cell::RefCell, rc::Rc, sync::{Arc, Mutex}};
+use std::{borrow::BorrowMut, cell::{Cell, RefCell}, collections::{HashMap, VecDeque}, rc::Rc, sync::{Arc, Mutex}};
+use js_sys::Function;
+use serde::Serialize;
 use sysinfo::{Components, System};
 use tokio::time::{interval, Duration};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::spawn_local;
 
+/// The severity of a metric's current reading, derived from the configured
+/// `Thresholds` for that metric.
+///
+/// Mirrors the info/warning/critical model used by i3status's CPU block: the
+/// highest threshold the value has crossed wins, defaulting to `Idle` when
+/// the value is below the lowest configured threshold.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Severity {
+    Idle,
+    Info,
+    Warning,
+    Critical,
+}
+
+/// Ascending info/warning/critical thresholds for a single metric.
+///
+/// # Members
+///
+/// * `info: f64` - Value at and above which the metric is `Severity::Info`.
+/// * `warning: f64` - Value at and above which the metric is `Severity::Warning`.
+/// * `critical: f64` - Value at and above which the metric is `Severity::Critical`.
+#[derive(Clone, Copy)]
+struct Thresholds {
+    info: f64,
+    warning: f64,
+    critical: f64,
+}
+
+impl Thresholds {
+    /// Classify `value` against these thresholds, highest crossed wins.
+    fn classify(&self, value: f64) -> Severity {
+        if value >= self.critical {
+            Severity::Critical
+        } else if value >= self.warning {
+            Severity::Warning
+        } else if value >= self.info {
+            Severity::Info
+        } else {
+            Severity::Idle
+        }
+    }
+}
+
+/// A value a `FormatTemplate` placeholder can be filled with.
+enum TemplateValue {
+    Number(f64),
+    Text(String),
+}
+
+/// One piece of a parsed `FormatTemplate`: either literal text copied
+/// through unchanged, or a named placeholder with an optional precision
+/// (the `.N` in `{usage:.1}`).
+#[derive(Clone)]
+enum TemplatePart {
+    Literal(String),
+    Placeholder { name: String, precision: Option<usize> },
+}
+
+/// A user-defined format string such as `"CPU {usage:.1}%"`, parsed once at
+/// config time and filled with named values on every update. This is the
+/// `FormatTemplate` idea from i3status-rs: it lets users customize the
+/// output (including icon tokens like `{icon}`) without recompiling.
+#[derive(Clone)]
+struct FormatTemplate(Vec<TemplatePart>);
+
+impl FormatTemplate {
+    fn parse(template: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            if c == '{' {
+                if !literal.is_empty() {
+                    parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                }
+                let mut token = String::new();
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                    token.push(c);
+                }
+                let (name, precision) = match token.split_once(':') {
+                    Some((name, spec)) => {
+                        let precision = spec.strip_prefix('.').and_then(|p| p.parse().ok());
+                        (name.to_string(), precision)
+                    }
+                    None => (token, None),
+                };
+                parts.push(TemplatePart::Placeholder { name, precision });
+            } else {
+                literal.push(c);
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+        FormatTemplate(parts)
+    }
+
+    fn render(&self, values: &HashMap<&str, TemplateValue>) -> String {
+        let mut out = String::new();
+        for part in &self.0 {
+            match part {
+                TemplatePart::Literal(text) => out.push_str(text),
+                TemplatePart::Placeholder { name, precision } => {
+                    if let Some(value) = values.get(name.as_str()) {
+                        match value {
+                            TemplateValue::Number(n) => match precision {
+                                Some(p) => out.push_str(&format!("{:.*}", p, n)),
+                                None => out.push_str(&n.to_string()),
+                            },
+                            TemplateValue::Text(text) => out.push_str(text),
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
 /// Hold user configuration
 ///
 /// # Members
 ///
 /// * `update_interval: u64` - The interval in which sysmon should fetch data (in milliseconds).
-/// * `use_icons: bool` - Whether to use unicode symbols instead of text labels.
+/// * `cpu_format: FormatTemplate` - Template used to render the CPU label, e.g. `"CPU {usage:.2}%"`.
+/// * `mem_format: FormatTemplate` - Template used to render the memory label.
+/// * `temp_format: FormatTemplate` - Template used to render the temperature label.
+/// * `cpu_thresholds: Thresholds` - Info/warning/critical thresholds for CPU usage (%).
+/// * `mem_thresholds: Thresholds` - Info/warning/critical thresholds for used-memory fraction.
+/// * `temp_thresholds: Thresholds` - Info/warning/critical thresholds for temperature (°C).
+/// * `history_len: usize` - Number of samples kept in the CPU/mem rolling history buffers.
+/// * `cpu_enabled: bool` - Whether to refresh and report CPU usage.
+/// * `mem_enabled: bool` - Whether to refresh and report memory usage.
+/// * `temp_enabled: bool` - Whether to refresh and report temperature.
 #[derive(Clone)]
 struct Config {
     update_interval: u64,
-    use_icons: bool,
+    cpu_format: FormatTemplate,
+    mem_format: FormatTemplate,
+    temp_format: FormatTemplate,
+    cpu_thresholds: Thresholds,
+    mem_thresholds: Thresholds,
+    temp_thresholds: Thresholds,
+    history_len: usize,
+    cpu_enabled: bool,
+    mem_enabled: bool,
+    temp_enabled: bool,
 }
 
 impl Default for Config {
     fn default() -> Config {
         Config {
             update_interval: 2000,
-            use_icons: false,
+            cpu_format: FormatTemplate::parse("CPU: {usage:.2}%"),
+            mem_format: FormatTemplate::parse("Mem: {used:.2}/{total:.2} GB"),
+            temp_format: FormatTemplate::parse("Temp: {temp:.2}°C"),
+            cpu_thresholds: Thresholds {
+                info: 30.0,
+                warning: 60.0,
+                critical: 90.0,
+            },
+            mem_thresholds: Thresholds {
+                info: 0.5,
+                warning: 0.75,
+                critical: 0.9,
+            },
+            temp_thresholds: Thresholds {
+                info: 50.0,
+                warning: 70.0,
+                critical: 85.0,
+            },
+            history_len: 32,
+            cpu_enabled: true,
+            mem_enabled: true,
+            temp_enabled: true,
         }
     }
 }
@@ -34,13 +198,56 @@ impl Default for Config {
 struct SysMonState {
     config: Config,
     sys: System,
+    components: Components,
     cpu: String,
+    cpu_state: Severity,
+    cpu_percent: f32,
+    cpu_per_core: Vec<f32>,
+    cpu_history: VecDeque<f64>,
+    load_average: (f64, f64, f64),
     mem: String,
+    mem_state: Severity,
+    mem_used_bytes: u64,
+    mem_total_bytes: u64,
+    mem_history: VecDeque<f64>,
     temp: String,
+    temp_state: Severity,
+    temp_celsius: Option<f32>,
 }
 
 /// Icons to use when `use_icons` is true.
-const ICONS: (&str, &str, &str) = ("", "", "");
+const ICONS: (&str, &str, &str) = ("", "", "");
+
+/// The eight block-level glyphs used by cpuline-style sparklines, plus a
+/// leading space for a zero reading.
+const SPARKLINE_GLYPHS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Push `value` onto the back of a fixed-size ring buffer, dropping the
+/// oldest sample once `capacity` is exceeded.
+fn push_sample(history: &mut VecDeque<f64>, value: f64, capacity: usize) {
+    history.push_back(value);
+    while history.len() > capacity {
+        history.pop_front();
+    }
+}
+
+/// Render a history buffer as a sparkline, mapping each sample to one of
+/// nine levels by scaling it against `fixed_max` (e.g. 0-100 for a
+/// percentage) or, if `None`, against the window's own current max.
+fn render_sparkline(history: &VecDeque<f64>, fixed_max: Option<f64>) -> String {
+    let max = fixed_max.unwrap_or_else(|| history.iter().cloned().fold(0.0, f64::max));
+    history
+        .iter()
+        .map(|&value| {
+            if max <= 0.0 {
+                SPARKLINE_GLYPHS[0]
+            } else {
+                let level = ((value / max) * (SPARKLINE_GLYPHS.len() - 1) as f64).round() as usize;
+                SPARKLINE_GLYPHS[level.min(SPARKLINE_GLYPHS.len() - 1)]
+            }
+        })
+        .collect()
+}
 
 impl SysMonState {
     fn new(config: Config) -> Self {
@@ -49,61 +256,166 @@ impl SysMonState {
         SysMonState {
             config,
             sys,
+            components: Components::new_with_refreshed_list(),
             cpu: String::new(),
+            cpu_state: Severity::Idle,
+            cpu_percent: 0.0,
+            cpu_per_core: Vec::new(),
+            cpu_history: VecDeque::new(),
+            load_average: (0.0, 0.0, 0.0),
             mem: String::new(),
+            mem_state: Severity::Idle,
+            mem_used_bytes: 0,
+            mem_total_bytes: 0,
+            mem_history: VecDeque::new(),
             temp: String::new(),
+            temp_state: Severity::Idle,
+            temp_celsius: None,
         }
     }
 
     pub fn update_cpu_usage(&mut self) {
         self.sys.refresh_cpu_all();
         let cpu = self.sys.global_cpu_usage();
-        if self.config.use_icons {
-            self.cpu = format!("{} {:.2}%", ICONS.0, cpu);
-        } else {
-            self.cpu = format!("CPU: {:.2}%", cpu);
-        }
+        self.cpu_percent = cpu;
+        self.cpu_per_core = self.sys.cpus().iter().map(|core| core.cpu_usage()).collect();
+        push_sample(&mut self.cpu_history, cpu as f64, self.config.history_len);
+        let load_avg = System::load_average();
+        self.load_average = (load_avg.one, load_avg.five, load_avg.fifteen);
+        self.cpu_state = self.config.cpu_thresholds.classify(cpu as f64);
+        let values = HashMap::from([
+            ("usage", TemplateValue::Number(cpu as f64)),
+            ("icon", TemplateValue::Text(ICONS.0.to_string())),
+        ]);
+        self.cpu = self.config.cpu_format.render(&values);
     }
 
     pub fn update_mem_usage(&mut self) {
         self.sys.refresh_memory();
+        self.mem_used_bytes = self.sys.used_memory();
+        self.mem_total_bytes = self.sys.total_memory();
         let total_mem = self.sys.total_memory() as f64 / 1024.0;
         let used_mem = self.sys.used_memory() as f64 / 1024.0;
-        if self.config.use_icons {
-            self.mem = format!("{} {:.2}/{:.2} GB", ICONS.1, used_mem, total_mem);
+        let used_fraction = if total_mem > 0.0 {
+            used_mem / total_mem
         } else {
-            self.mem = format!("Mem: {:.2}/{:.2} GB", used_mem, total_mem);
-        }
+            0.0
+        };
+        self.mem_state = self.config.mem_thresholds.classify(used_fraction);
+        push_sample(&mut self.mem_history, used_fraction * 100.0, self.config.history_len);
+        let values = HashMap::from([
+            ("used", TemplateValue::Number(used_mem)),
+            ("total", TemplateValue::Number(total_mem)),
+            ("icon", TemplateValue::Text(ICONS.1.to_string())),
+        ]);
+        self.mem = self.config.mem_format.render(&values);
     }
 
     pub fn update_temp(&mut self) {
-        let components = Components::new_with_refreshed_list();
-        if let Some(component) = components.get(0) {
+        self.components.refresh(true);
+        if let Some(component) = self.components.get(0) {
             let temp = component.temperature();
-            if self.config.use_icons {
-                self.temp = format!("{} {:.2}°C", ICONS.2, temp);
-            } else {
-                self.temp = format!("Temp: {:.2}°C", temp);
-            }
+            self.temp_celsius = Some(temp);
+            self.temp_state = self.config.temp_thresholds.classify(temp as f64);
+            let values = HashMap::from([
+                ("temp", TemplateValue::Number(temp as f64)),
+                ("icon", TemplateValue::Text(ICONS.2.to_string())),
+            ]);
+            self.temp = self.config.temp_format.render(&values);
         }
     }
 
     pub fn update_all(&mut self) {
-        self.update_cpu_usage();
-        self.update_mem_usage();
-        self.update_temp();
+        if self.config.cpu_enabled {
+            self.update_cpu_usage();
+        }
+        if self.config.mem_enabled {
+            self.update_mem_usage();
+        }
+        if self.config.temp_enabled {
+            self.update_temp();
+        }
     }
 }
 
+/// Structured system stats handed to JS as a plain object (see the swaybar
+/// JSON protocol: each module emits typed fields plus a formatted label and
+/// a severity `state`, rather than one flat string the caller re-parses).
+///
+/// `cpu_per_core` and the `load_average_*` fields let a dashboard draw a
+/// per-core bar chart instead of relying on the single global percentage,
+/// which can hide a hot core on multi-core machines.
+#[derive(Serialize)]
+pub struct SysStats {
+    pub cpu_percent: f32,
+    pub cpu_per_core: Vec<f32>,
+    pub cpu_history: Vec<f64>,
+    pub cpu_sparkline: String,
+    pub load_average_1m: f64,
+    pub load_average_5m: f64,
+    pub load_average_15m: f64,
+    pub cpu_label: String,
+    pub cpu_state: Severity,
+    pub mem_used_bytes: u64,
+    pub mem_total_bytes: u64,
+    pub mem_history: Vec<f64>,
+    pub mem_sparkline: String,
+    pub mem_label: String,
+    pub mem_state: Severity,
+    pub temp_celsius: Option<f32>,
+    pub temp_label: String,
+    pub temp_state: Severity,
+}
+
 thread_local! {
     static SYSMON_STATE: Rc<RefCell<Option<SysMonState>>> = Rc::new(RefCell::new(None));
 }
 
 #[wasm_bindgen()]
-pub fn init(update_interval: u64, use_icons: bool) -> Result<(), JsValue> {
+#[allow(clippy::too_many_arguments)]
+pub fn init(
+    update_interval: u64,
+    cpu_format: String,
+    mem_format: String,
+    temp_format: String,
+    cpu_info: f64,
+    cpu_warning: f64,
+    cpu_critical: f64,
+    mem_info: f64,
+    mem_warning: f64,
+    mem_critical: f64,
+    temp_info: f64,
+    temp_warning: f64,
+    temp_critical: f64,
+    history_len: usize,
+    cpu_enabled: bool,
+    mem_enabled: bool,
+    temp_enabled: bool,
+) -> Result<(), JsValue> {
     let config = Config {
         update_interval,
-        use_icons,
+        cpu_format: FormatTemplate::parse(&cpu_format),
+        mem_format: FormatTemplate::parse(&mem_format),
+        temp_format: FormatTemplate::parse(&temp_format),
+        cpu_thresholds: Thresholds {
+            info: cpu_info,
+            warning: cpu_warning,
+            critical: cpu_critical,
+        },
+        mem_thresholds: Thresholds {
+            info: mem_info,
+            warning: mem_warning,
+            critical: mem_critical,
+        },
+        temp_thresholds: Thresholds {
+            info: temp_info,
+            warning: temp_warning,
+            critical: temp_critical,
+        },
+        history_len,
+        cpu_enabled,
+        mem_enabled,
+        temp_enabled,
     };
 
     SYSMON_STATE.with(|sysmon_state| {
@@ -112,12 +424,104 @@ pub fn init(update_interval: u64, use_icons: bool) -> Result<(), JsValue> {
     Ok(())
 }
 
+/// Refresh every enabled metric and serialize the result for JS, shared by
+/// the pull-based `get_sys_stats` and the push-based `start` loop.
+fn build_sys_stats() -> Result<JsValue, JsValue> {
+    SYSMON_STATE.with(|sysmon_state| {
+        if let Some(ref mut state) = *std::cell::RefCell::<_>::borrow_mut(sysmon_state) {
+            state.update_all();
+            let stats = SysStats {
+                cpu_percent: state.cpu_percent,
+                cpu_per_core: state.cpu_per_core.clone(),
+                cpu_history: state.cpu_history.iter().cloned().collect(),
+                cpu_sparkline: render_sparkline(&state.cpu_history, Some(100.0)),
+                load_average_1m: state.load_average.0,
+                load_average_5m: state.load_average.1,
+                load_average_15m: state.load_average.2,
+                cpu_label: state.cpu.clone(),
+                cpu_state: state.cpu_state,
+                mem_used_bytes: state.mem_used_bytes,
+                mem_total_bytes: state.mem_total_bytes,
+                mem_history: state.mem_history.iter().cloned().collect(),
+                mem_sparkline: render_sparkline(&state.mem_history, None),
+                mem_label: state.mem.clone(),
+                mem_state: state.mem_state,
+                temp_celsius: state.temp_celsius,
+                temp_label: state.temp.clone(),
+                temp_state: state.temp_state,
+            };
+            serde_wasm_bindgen::to_value(&stats).map_err(|e| JsValue::from_str(&e.to_string()))
+        } else {
+            Err(JsValue::from_str("System monitor not initialized"))
+        }
+    })
+}
+
 #[wasm_bindgen()]
 pub fn get_sys_stats() -> Result<JsValue, JsValue> {
+    build_sys_stats()
+}
+
+/// Handle returned by `start`, used to stop its update loop.
+#[wasm_bindgen]
+pub struct IntervalHandle {
+    stopped: Rc<Cell<bool>>,
+}
+
+#[wasm_bindgen]
+impl IntervalHandle {
+    pub fn stop(&self) {
+        self.stopped.set(true);
+    }
+}
+
+/// Spawn a local task that ticks at `config.update_interval`, refreshes the
+/// stats and invokes `callback` with them each tick. Turns the crate from
+/// pure pull (`get_sys_stats`) into an event-driven source, the way a status
+/// bar like swayr runs a refresh loop and streams blocks to its renderer.
+#[wasm_bindgen]
+pub fn start(callback: Function) -> Result<IntervalHandle, JsValue> {
+    let update_interval = SYSMON_STATE.with(|sysmon_state| {
+        std::cell::RefCell::<_>::borrow(sysmon_state)
+            .as_ref()
+            .map(|state| state.config.update_interval)
+    });
+    let Some(update_interval) = update_interval else {
+        return Err(JsValue::from_str("System monitor not initialized"));
+    };
+
+    let stopped = Rc::new(Cell::new(false));
+    let handle = IntervalHandle {
+        stopped: stopped.clone(),
+    };
+
+    spawn_local(async move {
+        let mut ticker = interval(Duration::from_millis(update_interval));
+        while !stopped.get() {
+            ticker.tick().await;
+            if stopped.get() {
+                break;
+            }
+            match build_sys_stats() {
+                Ok(stats) => {
+                    let _ = callback.call1(&JsValue::NULL, &stats);
+                }
+                Err(_) => break,
+            }
+        }
+    });
+
+    Ok(handle)
+}
+
+/// Backward-compatible pipe-joined text, for callers that haven't moved to
+/// the structured `get_sys_stats` output yet.
+#[wasm_bindgen()]
+pub fn get_sys_stats_text() -> Result<JsValue, JsValue> {
     SYSMON_STATE.with(|sysmon_state| {
         if let Some(ref mut state) = *std::cell::RefCell::<_>::borrow_mut(sysmon_state) {
             state.update_all();
-            let result = format!("{} | {} | {}", state.cpu, state.mem, state.temp);
+            let result = format!("{} | {} | {}", state.cpu, state.mem, state.temp);
             Ok(JsValue::from_str(&result))
         } else {
             Err(JsValue::from_str("System monitor not initialized"))